@@ -0,0 +1,63 @@
+//! A minimal Bloom filter used for anti-entropy pull requests (see `daemon.rs`).
+//!
+//! Sized from the number of local records and a target false-positive rate, with
+//! positions derived via double hashing (`h_i = h1 + i*h2`, where `h1` is the element's
+//! digest and `h2` a bit rotation of it) so only one hash is computed per element
+//! regardless of `num_hashes`.
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use serde::{Deserialize, Serialize};
+
+/// Space-efficient, probabilistic set membership test.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BloomFilter {
+    bits: Vec<u64>,
+    num_bits: usize,
+    num_hashes: u32,
+}
+
+impl BloomFilter {
+    /// Build a filter sized for `expected_items` elements at `false_positive_rate`
+    /// (e.g. `0.01` for ~1%).
+    pub fn new(expected_items: usize, false_positive_rate: f64) -> Self {
+        let n = expected_items.max(1) as f64;
+        let p = false_positive_rate.clamp(f64::MIN_POSITIVE, 0.5);
+        let num_bits = (-(n * p.ln()) / std::f64::consts::LN_2.powi(2)).ceil().max(8.0) as usize;
+        let num_hashes = ((num_bits as f64 / n) * std::f64::consts::LN_2).round().max(1.0) as u32;
+        Self {
+            bits: vec![0u64; num_bits.div_ceil(64)],
+            num_bits,
+            num_hashes,
+        }
+    }
+
+    /// Add a digest to the filter.
+    pub fn insert(&mut self, digest: u64) {
+        for pos in self.positions(digest) {
+            self.bits[pos / 64] |= 1 << (pos % 64);
+        }
+    }
+
+    /// Check whether a digest is (probably) present.
+    pub fn contains(&self, digest: u64) -> bool {
+        self.positions(digest)
+            .all(|pos| self.bits[pos / 64] & (1 << (pos % 64)) != 0)
+    }
+
+    fn positions(&self, digest: u64) -> impl Iterator<Item = usize> + '_ {
+        let h1 = digest;
+        let h2 = digest.rotate_left(32) | 1; // keep h2 odd so it can't degenerate to 0
+        let num_bits = self.num_bits as u64;
+        (0..self.num_hashes).map(move |i| (h1.wrapping_add(i as u64 * h2) % num_bits) as usize)
+    }
+}
+
+/// Digest a `(node_id, version)` pair for membership testing in a [`BloomFilter`].
+pub fn digest(node_id: &str, version: u64) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    node_id.hash(&mut hasher);
+    version.hash(&mut hasher);
+    hasher.finish()
+}
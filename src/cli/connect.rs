@@ -0,0 +1,44 @@
+use anyhow::{Context, Result};
+use iroh::EndpointId;
+
+use crate::node::ClawNode;
+use crate::output::{self, ConnectOutput};
+use crate::protocol;
+use crate::store;
+
+pub async fn run(node_id_str: &str, json: bool) -> Result<()> {
+    let node = ClawNode::spawn().await?;
+
+    let target: EndpointId = node_id_str.parse().context("invalid node ID")?;
+
+    // Try known addresses (most-recently-seen first) before falling back to discovery.
+    if let Some(peer) = store::get(node_id_str).ok().flatten() {
+        let direct_addresses: Vec<_> = peer.addresses.iter().filter_map(|a| a.parse().ok()).collect();
+        if !direct_addresses.is_empty() {
+            let node_addr = iroh::NodeAddr::from_parts(target, None, direct_addresses);
+            let _ = node.endpoint.add_node_addr(node_addr);
+        }
+    }
+
+    if !json {
+        eprintln!("Connecting to {node_id_str}...");
+    }
+
+    let connection = node
+        .endpoint
+        .connect(target, protocol::MSG_ALPN)
+        .await
+        .context("failed to connect to peer")?;
+    connection.close(0u32.into(), b"done");
+
+    output::print(
+        &ConnectOutput {
+            status: "connected".to_string(),
+            node_id: node_id_str.to_string(),
+        },
+        json,
+    );
+
+    node.shutdown().await?;
+    Ok(())
+}
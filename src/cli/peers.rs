@@ -0,0 +1,26 @@
+use anyhow::Result;
+
+use crate::daemon::DEFAULT_LIVENESS_TIMEOUT_SECS;
+use crate::output::{self, PeerEntry, PeersOutput};
+use crate::store;
+
+pub fn run(online: bool, json: bool) -> Result<()> {
+    let mut peers = store::all().unwrap_or_default();
+    if online {
+        peers.retain(|p| !p.is_dead(DEFAULT_LIVENESS_TIMEOUT_SECS));
+    }
+
+    let entries: Vec<PeerEntry> = peers
+        .iter()
+        .map(|p| PeerEntry {
+            node_id: p.node_id.clone(),
+            name: p.name.clone(),
+            capabilities: p.capabilities.clone(),
+            online: !p.is_dead(DEFAULT_LIVENESS_TIMEOUT_SECS),
+            last_seen: p.last_seen,
+        })
+        .collect();
+
+    output::print(&PeersOutput { peers: entries }, json);
+    Ok(())
+}
@@ -1,9 +1,19 @@
+use std::time::Duration;
+
 use anyhow::{Context, Result};
 use iroh::EndpointId;
 
+use crate::config;
+use crate::daemon;
 use crate::node::ClawNode;
 use crate::output::{self, SendOutput};
-use crate::protocol::{self, DirectMessage};
+use crate::protocol::{self, DirectMessage, RelayRequest, RpcResponse};
+use crate::rpc;
+use crate::store;
+
+/// A `DaemonStatus` snapshot older than this many write intervals is treated as stale,
+/// left behind by a daemon that didn't shut down cleanly rather than a live one.
+const STALE_AFTER_INTERVALS: u64 = 3;
 
 pub async fn run(node_id_str: &str, message: &str, json: bool) -> Result<()> {
     let node = ClawNode::spawn().await?;
@@ -12,76 +22,104 @@ pub async fn run(node_id_str: &str, message: &str, json: bool) -> Result<()> {
         .parse()
         .context("invalid node ID")?;
 
+    // Try known addresses (most-recently-seen first) before falling back to discovery.
+    if let Some(peer) = store::get(node_id_str).ok().flatten() {
+        let direct_addresses: Vec<_> = peer.addresses.iter().filter_map(|a| a.parse().ok()).collect();
+        if !direct_addresses.is_empty() {
+            let node_addr = iroh::NodeAddr::from_parts(target, None, direct_addresses);
+            let _ = node.endpoint.add_node_addr(node_addr);
+        }
+    }
+
     if !json {
         eprintln!("Sending message to {node_id_str}...");
     }
 
-    let connection = node
-        .endpoint
-        .connect(target, protocol::MSG_ALPN)
-        .await
-        .context("failed to connect to peer")?;
-
-    let (mut send_stream, mut recv_stream) = connection
-        .open_bi()
-        .await
-        .context("failed to open bidirectional stream")?;
-
     let msg = DirectMessage {
         from: node.endpoint.id().to_string(),
         content: message.to_string(),
         timestamp: protocol::now_secs(),
     };
+    let payload = msg.to_bytes();
+    let bytes_sent = payload.len();
 
-    let bytes = msg.to_bytes();
-    let bytes_len = bytes.len();
-
-    // Send length-prefixed message
-    send_stream
-        .write_all(&(bytes.len() as u32).to_be_bytes())
-        .await
-        .context("failed to send message length")?;
-    send_stream
-        .write_all(&bytes)
+    // Prefer handing the payload to an already-running daemon, which keeps a standing mesh
+    // link to the target and can deliver without this short-lived process dialing fresh.
+    // Fall back to a direct dial if no live daemon is found, or if one was found but its
+    // `relay` call itself failed (e.g. no mesh link to the target yet) — a relay `Err` is a
+    // miss, not a send, so it must not be reported as "sent".
+    let relayed = try_relay_via_daemon(&node, node_id_str, payload.clone())
         .await
-        .context("failed to send message")?;
-    send_stream.finish().context("failed to finish stream")?;
-
-    // Try to read response
-    let response = match tokio::time::timeout(
-        std::time::Duration::from_secs(5),
-        read_response(&mut recv_stream),
-    )
-    .await
-    {
-        Ok(Ok(resp)) => Some(resp),
-        _ => None,
+        .and_then(|resp| resp.result.ok());
+    let response = match relayed {
+        Some(bytes) => Some(String::from_utf8_lossy(&bytes).into_owned()),
+        None => {
+            let connection = node
+                .endpoint
+                .connect(target, protocol::MSG_ALPN)
+                .await
+                .context("failed to connect to peer")?;
+
+            // Try the `message` RPC method; an unreachable or slow peer just yields no response.
+            let response = match tokio::time::timeout(
+                Duration::from_secs(5),
+                rpc::call(&connection, "message", payload),
+            )
+            .await
+            {
+                Ok(Ok(resp)) => resp.result.ok().map(|bytes| String::from_utf8_lossy(&bytes).into_owned()),
+                _ => None,
+            };
+
+            connection.close(0u32.into(), b"done");
+            response
+        }
     };
 
     output::print(
         &SendOutput {
             status: "sent".to_string(),
             node_id: node_id_str.to_string(),
-            bytes_sent: bytes_len,
+            bytes_sent,
             response,
         },
         json,
     );
 
-    connection.close(0u32.into(), b"done");
     node.shutdown().await?;
     Ok(())
 }
 
-async fn read_response(recv: &mut iroh::endpoint::RecvStream) -> Result<String> {
-    let mut len_buf = [0u8; 4];
-    recv.read_exact(&mut len_buf).await?;
-    let len = u32::from_be_bytes(len_buf) as usize;
-    if len > 1024 * 1024 {
-        anyhow::bail!("response too large");
+/// Relay `payload` to `node_id_str` through a locally running daemon's `relay` RPC method,
+/// reusing whatever standing mesh link it already has. Returns `None` if no daemon status
+/// snapshot exists, it's stale, the daemon can't be dialed, or the RPC round trip fails, so
+/// the caller can fall back to dialing the target directly. A relay that the daemon itself
+/// rejects comes back as `Some` with an `Err` result, which the caller also treats as a miss.
+async fn try_relay_via_daemon(
+    node: &ClawNode,
+    node_id_str: &str,
+    payload: Vec<u8>,
+) -> Option<RpcResponse> {
+    let status = config::read_daemon_status().ok().flatten()?;
+    if protocol::now_secs().saturating_sub(status.updated_at)
+        > daemon::STATUS_WRITE_INTERVAL_SECS * STALE_AFTER_INTERVALS
+    {
+        return None;
+    }
+
+    let daemon_id: EndpointId = status.node_id.parse().ok()?;
+    let direct_addresses: Vec<_> = status.addresses.iter().filter_map(|a| a.parse().ok()).collect();
+    if !direct_addresses.is_empty() {
+        let node_addr = iroh::NodeAddr::from_parts(daemon_id, None, direct_addresses);
+        let _ = node.endpoint.add_node_addr(node_addr);
     }
-    let mut buf = vec![0u8; len];
-    recv.read_exact(&mut buf).await?;
-    let msg = DirectMessage::from_bytes(&buf)?;
-    Ok(msg.content)
+
+    let connection = node.endpoint.connect(daemon_id, protocol::MSG_ALPN).await.ok()?;
+    let request = RelayRequest {
+        target: node_id_str.to_string(),
+        payload,
+    };
+    let response = rpc::call(&connection, "relay", request.to_bytes()).await.ok();
+    connection.close(0u32.into(), b"done");
+    response
 }
@@ -0,0 +1,51 @@
+use anyhow::Result;
+
+use crate::daemon::{self, DEFAULT_LIVENESS_TIMEOUT_SECS};
+use crate::output::{self, StatusOutput};
+use crate::{config, protocol, store};
+
+/// A status snapshot older than this many write intervals is treated as a stale file left
+/// by a daemon that didn't shut down cleanly, not a live one.
+const STALE_AFTER_INTERVALS: u64 = 3;
+
+pub fn run(json: bool) -> Result<()> {
+    let daemon_status = config::read_daemon_status()?.filter(|status| {
+        protocol::now_secs().saturating_sub(status.updated_at)
+            <= daemon::STATUS_WRITE_INTERVAL_SECS * STALE_AFTER_INTERVALS
+    });
+
+    // The peer store is a local cache independent of whether a daemon is currently running,
+    // so liveness can be reported either way.
+    let peers = store::all().unwrap_or_default();
+    let peers_cached = peers.len();
+    let peers_online = peers
+        .iter()
+        .filter(|p| !p.is_dead(DEFAULT_LIVENESS_TIMEOUT_SECS))
+        .count();
+
+    let out = match daemon_status {
+        Some(status) => StatusOutput {
+            running: true,
+            node_id: Some(status.node_id),
+            uptime_secs: Some(status.updated_at.saturating_sub(status.start_time)),
+            announcements_sent: Some(status.announcements_sent),
+            peers_discovered: Some(status.peers_discovered),
+            mesh_size: Some(status.mesh_size),
+            peers_cached,
+            peers_online,
+        },
+        None => StatusOutput {
+            running: false,
+            node_id: None,
+            uptime_secs: None,
+            announcements_sent: None,
+            peers_discovered: None,
+            mesh_size: None,
+            peers_cached,
+            peers_online,
+        },
+    };
+
+    output::print(&out, json);
+    Ok(())
+}
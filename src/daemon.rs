@@ -6,18 +6,63 @@ use anyhow::Result;
 use futures_lite::StreamExt;
 use iroh_gossip::api::Event;
 
+use crate::bloom::BloomFilter;
 use crate::config;
 use crate::gossip;
+use crate::handler::MessageHandler;
+use crate::mesh::MeshManager;
 use crate::node::ClawNode;
-use crate::protocol::{self, BotAnnouncement, DirectMessage, GossipMessage, PeerInfo, MSG_ALPN};
+use crate::protocol::{
+    self, BotAnnouncement, DaemonStatus, DirectMessage, GossipMessage, PeerInfo, RelayRequest,
+    SignedAnnouncement,
+};
+use crate::rpc::{RpcMessageHandler, RpcRegistry};
 use crate::store;
 
+/// How often a node pulls anti-entropy from a sample of its peer store.
+const PULL_INTERVAL_SECS: u64 = 45;
+/// Number of random peers pulled from on each anti-entropy round.
+const PULL_FANOUT: usize = 3;
+/// Target false-positive rate for the anti-entropy Bloom filter.
+const BLOOM_FALSE_POSITIVE_RATE: f64 = 0.01;
+/// Default liveness timeout: a peer with no traffic in this long is reported dead by
+/// `Peers --online`/`Status`, well before its announced TTL would expire it outright.
+pub const DEFAULT_LIVENESS_TIMEOUT_SECS: u64 = 300;
+/// How often the full-mesh manager reconciles its links against the peer store.
+const MESH_RECONCILE_INTERVAL_SECS: u64 = 15;
+/// How often the daemon persists a [`DaemonStatus`] snapshot to disk so a separate,
+/// short-lived process (`clawnet status`, `clawnet send`) has something to read; see
+/// [`config::write_daemon_status`].
+pub const STATUS_WRITE_INTERVAL_SECS: u64 = 10;
+
+/// CRDT last-writer-wins check: true if `new_version` is newer than the store's current
+/// record for this peer (or there is no current record). A lower-or-equal version is a
+/// stale or replayed announcement and must not overwrite the newer record already held, nor
+/// refresh its liveness.
+fn is_newer(existing: Option<&PeerInfo>, new_version: u64) -> bool {
+    existing.map(|p| new_version > p.record_version).unwrap_or(true)
+}
+
+/// Collect the local endpoint's own observed direct addresses, to gossip out so peers have
+/// a fallback dial path when discovery alone isn't enough.
+async fn local_addresses(endpoint: &iroh::Endpoint) -> Vec<String> {
+    match endpoint.node_addr().await {
+        Ok(addr) => addr.direct_addresses().map(|a| a.to_string()).collect(),
+        Err(e) => {
+            tracing::debug!("failed to read local direct addresses: {e}");
+            Vec::new()
+        }
+    }
+}
+
 /// Shared daemon state for status queries.
 pub struct DaemonState {
     pub running: AtomicBool,
     pub announcements_sent: AtomicU64,
     pub peers_discovered: AtomicU64,
     pub start_time: u64,
+    /// Tiebreaker counter for [`protocol::next_record_version`].
+    pub record_version_counter: AtomicU64,
 }
 
 impl DaemonState {
@@ -27,29 +72,168 @@ impl DaemonState {
             announcements_sent: AtomicU64::new(0),
             peers_discovered: AtomicU64::new(0),
             start_time: protocol::now_secs(),
+            record_version_counter: AtomicU64::new(0),
         }
     }
 }
 
+/// Build the RPC registry with clawnet's built-in methods: `ping`, `describe` (the local
+/// `BotAnnouncement`), `peers` (cached `PeerInfo` records), `message` (print a
+/// `DirectMessage` and ack it), `relay` (hand a payload to [`MeshManager::send_to`] on the
+/// caller's behalf), and `pull` (anti-entropy: the requester's Bloom filter in, the signed
+/// records it's missing out). The daemon serves exactly these methods; embedders extend it
+/// with [`MessageHandler`]s on their own ALPNs instead.
+fn builtin_registry(
+    node_id: String,
+    cfg: config::Config,
+    state: Arc<DaemonState>,
+    endpoint: iroh::Endpoint,
+    mesh: Arc<MeshManager>,
+) -> RpcRegistry {
+    let mut registry = RpcRegistry::new();
+
+    registry.register("ping", |_payload| async { Ok(Vec::new()) });
+
+    registry.register("peers", |_payload| async {
+        Ok(postcard::to_allocvec(&store::all().unwrap_or_default())?)
+    });
+
+    registry.register("message", |payload| async move {
+        let msg = DirectMessage::from_bytes(&payload)?;
+        tracing::info!(from = %msg.from, "received direct message");
+        eprintln!(
+            "Message from {}: {}",
+            &msg.from[..16.min(msg.from.len())],
+            msg.content
+        );
+        Ok(b"received".to_vec())
+    });
+
+    registry.register("relay", move |payload| {
+        let mesh = mesh.clone();
+        async move {
+            let req = RelayRequest::from_bytes(&payload)?;
+            mesh.send_to(&req.target, req.payload).await?;
+            Ok(b"relayed".to_vec())
+        }
+    });
+
+    registry.register("pull", |payload| async move {
+        let filter: BloomFilter = postcard::from_bytes(&payload)?;
+        let records: Vec<SignedAnnouncement> = store::all()
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|peer| !filter.contains(crate::bloom::digest(&peer.node_id, peer.record_version)))
+            .map(|peer| peer.signed)
+            .collect();
+        Ok(postcard::to_allocvec(&records)?)
+    });
+
+    registry.register("describe", move |_payload| {
+        let node_id = node_id.clone();
+        let cfg = cfg.clone();
+        let state = state.clone();
+        let endpoint = endpoint.clone();
+        async move {
+            let ann = BotAnnouncement {
+                node_id,
+                name: cfg.name.clone(),
+                version: env!("CARGO_PKG_VERSION").to_string(),
+                capabilities: cfg.capabilities.clone(),
+                openclaw_version: cfg.openclaw_version.clone(),
+                mode: cfg.mode.clone(),
+                timestamp: protocol::now_secs(),
+                ttl: cfg.peer_ttl,
+                metadata: cfg.metadata.clone(),
+                record_version: protocol::next_record_version(&state.record_version_counter),
+                addresses: local_addresses(&endpoint).await,
+            };
+            Ok(postcard::to_allocvec(&ann)?)
+        }
+    });
+
+    registry
+}
+
 /// Run the continuous discovery daemon.
 pub async fn run(interval_secs: u64) -> Result<()> {
+    run_with_handlers(interval_secs, Vec::new()).await
+}
+
+/// Run the daemon with additional [`MessageHandler`]s registered alongside the built-in
+/// RPC handler, so an embedder can route its own ALPN-negotiated wire protocol through the
+/// same accept loop without forking clawnet.
+pub async fn run_with_handlers(
+    interval_secs: u64,
+    extra_handlers: Vec<Arc<dyn MessageHandler>>,
+) -> Result<()> {
     let cfg = config::load()?;
     let node = ClawNode::spawn().await?;
     let state = Arc::new(DaemonState::new());
 
     let node_id = node.endpoint.id().to_string();
+    let signing_key = node.endpoint.secret_key().clone();
     tracing::info!(node_id = %node_id, "daemon started");
     eprintln!("Daemon started. Node ID: {node_id}");
     eprintln!("Press Ctrl+C to stop.");
 
+    // Full-mesh peering manager: one standing connection per known, non-expired peer,
+    // reused by `send_to` instead of dialing fresh for every call.
+    let mesh = MeshManager::new(node.endpoint.clone(), |node_id, up| {
+        tracing::debug!(peer = %node_id, up, "mesh link transition");
+    });
+    let reconcile_mesh = mesh.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(MESH_RECONCILE_INTERVAL_SECS));
+        loop {
+            interval.tick().await;
+            reconcile_mesh.reconcile().await;
+        }
+    });
+
+    // Periodically persist a status snapshot so `clawnet status`/`clawnet send`, which run
+    // as separate short-lived processes, can read this daemon's live mesh size and counters.
+    let status_state = state.clone();
+    let status_node_id = node_id.clone();
+    let status_mesh = mesh.clone();
+    let status_endpoint = node.endpoint.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(STATUS_WRITE_INTERVAL_SECS));
+        loop {
+            interval.tick().await;
+            let status = DaemonStatus {
+                node_id: status_node_id.clone(),
+                addresses: local_addresses(&status_endpoint).await,
+                start_time: status_state.start_time,
+                updated_at: protocol::now_secs(),
+                announcements_sent: status_state.announcements_sent.load(Ordering::Relaxed),
+                peers_discovered: status_state.peers_discovered.load(Ordering::Relaxed),
+                mesh_size: status_mesh.live_count().await as u64,
+            };
+            if let Err(e) = config::write_daemon_status(&status) {
+                tracing::warn!("failed to persist daemon status: {e}");
+            }
+        }
+    });
+
     let topic = gossip::subscribe(&node.gossip, vec![]).await?;
     let (sender, mut receiver) = topic.split();
 
-    // Spawn message acceptor for direct connections
+    // Spawn the connection acceptor, routing each incoming connection to whichever
+    // registered handler claims its negotiated ALPN.
     let endpoint = node.endpoint.clone();
-    let accept_state = state.clone();
+    let registry = Arc::new(builtin_registry(
+        node_id.clone(),
+        cfg.clone(),
+        state.clone(),
+        endpoint.clone(),
+        mesh.clone(),
+    ));
+    let mut handlers: Vec<Arc<dyn MessageHandler>> = vec![Arc::new(RpcMessageHandler::new(registry))];
+    handlers.extend(extra_handlers);
+    let handlers = Arc::new(handlers);
     tokio::spawn(async move {
-        accept_loop(endpoint, accept_state).await;
+        accept_loop(endpoint, handlers).await;
     });
 
     // Periodic announcement task
@@ -57,11 +241,13 @@ pub async fn run(interval_secs: u64) -> Result<()> {
     let announce_cfg = cfg.clone();
     let announce_node_id = node_id.clone();
     let announce_state = state.clone();
+    let announce_key = signing_key.clone();
+    let announce_endpoint = node.endpoint.clone();
     tokio::spawn(async move {
         let mut interval = tokio::time::interval(Duration::from_secs(interval_secs));
         loop {
             interval.tick().await;
-            let ann = GossipMessage::Announce(BotAnnouncement {
+            let ann = BotAnnouncement {
                 node_id: announce_node_id.clone(),
                 name: announce_cfg.name.clone(),
                 version: env!("CARGO_PKG_VERSION").to_string(),
@@ -71,7 +257,10 @@ pub async fn run(interval_secs: u64) -> Result<()> {
                 timestamp: protocol::now_secs(),
                 ttl: announce_cfg.peer_ttl,
                 metadata: announce_cfg.metadata.clone(),
-            });
+                record_version: protocol::next_record_version(&announce_state.record_version_counter),
+                addresses: local_addresses(&announce_endpoint).await,
+            };
+            let ann = GossipMessage::signed_announce(ann, &announce_key);
             if let Err(e) = announce_sender.broadcast(ann.to_bytes().into()).await {
                 tracing::warn!("failed to broadcast announcement: {e}");
             } else {
@@ -82,6 +271,66 @@ pub async fn run(interval_secs: u64) -> Result<()> {
         }
     });
 
+    // Periodic anti-entropy pull task: sample a few peers and ask for records we're
+    // missing, repairing partitions and bootstrapping late joiners. Delivered as a `pull`
+    // RPC call over each target's mesh link rather than a gossip broadcast, since only the
+    // sampled peer ever acts on it — broadcasting it would flood the whole overlay with
+    // traffic meant for one recipient.
+    let pull_mesh = mesh.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(PULL_INTERVAL_SECS));
+        loop {
+            interval.tick().await;
+            let peers = store::all().unwrap_or_default();
+            if peers.is_empty() {
+                continue;
+            }
+            let mut filter = BloomFilter::new(peers.len(), BLOOM_FALSE_POSITIVE_RATE);
+            for peer in &peers {
+                filter.insert(crate::bloom::digest(&peer.node_id, peer.record_version));
+            }
+            let filter_bytes = postcard::to_allocvec(&filter).expect("serialization failed");
+
+            for target in store::sample(&peers, PULL_FANOUT) {
+                let mesh = pull_mesh.clone();
+                let filter_bytes = filter_bytes.clone();
+                tokio::spawn(async move {
+                    let response = match mesh.call(&target.node_id, "pull", filter_bytes).await {
+                        Ok(response) => response,
+                        Err(e) => {
+                            tracing::debug!(peer = %target.node_id, "pull call failed: {e}");
+                            return;
+                        }
+                    };
+                    let bytes = match response.result {
+                        Ok(bytes) => bytes,
+                        Err(e) => {
+                            tracing::debug!(peer = %target.node_id, "pull request failed: {e}");
+                            return;
+                        }
+                    };
+                    let records: Vec<SignedAnnouncement> = match postcard::from_bytes(&bytes) {
+                        Ok(records) => records,
+                        Err(e) => {
+                            tracing::debug!("failed to parse pull response: {e}");
+                            return;
+                        }
+                    };
+                    for record in records {
+                        if !record.verify() {
+                            continue;
+                        }
+                        let existing = store::get(&record.announcement.node_id).unwrap_or_default();
+                        if !is_newer(existing.as_ref(), record.announcement.record_version) {
+                            continue;
+                        }
+                        let _ = store::upsert(PeerInfo::from(&record));
+                    }
+                });
+            }
+        }
+    });
+
     // Listen for incoming gossip messages
     let listen_state = state.clone();
     let listen_node_id = node_id.clone();
@@ -89,11 +338,10 @@ pub async fn run(interval_secs: u64) -> Result<()> {
         tokio::select! {
             _ = tokio::signal::ctrl_c() => {
                 eprintln!("\nShutting down...");
-                // Send leave message
-                let leave = GossipMessage::Leave {
-                    node_id: listen_node_id.clone(),
-                    timestamp: protocol::now_secs(),
-                };
+                // Close mesh links gracefully before announcing departure, so peers see an
+                // orderly disconnect rather than a timeout.
+                mesh.shutdown().await;
+                let leave = GossipMessage::signed_leave(listen_node_id.clone(), protocol::now_secs(), &signing_key);
                 let _ = sender.broadcast(leave.to_bytes().into()).await;
                 break;
             }
@@ -101,21 +349,47 @@ pub async fn run(interval_secs: u64) -> Result<()> {
                 match event {
                     Ok(Some(Event::Received(msg))) => {
                         match GossipMessage::from_bytes(&msg.content) {
-                            Ok(GossipMessage::Announce(ann)) => {
+                            Ok(gossip_msg) if !gossip_msg.verify() => {
+                                tracing::warn!("dropping gossip message with invalid signature");
+                            }
+                            Ok(GossipMessage::Announce { announcement: ann, signature }) => {
                                 if ann.node_id == listen_node_id {
                                     continue;
                                 }
+                                let existing = store::get(&ann.node_id).unwrap_or_default();
+                                if !is_newer(existing.as_ref(), ann.record_version) {
+                                    tracing::debug!(peer = %ann.node_id, "dropping stale or replayed announcement");
+                                    continue;
+                                }
                                 tracing::info!(peer = %ann.node_id, name = %ann.name, "discovered peer");
                                 eprintln!("Discovered: {} ({})", ann.name, &ann.node_id[..16.min(ann.node_id.len())]);
-                                let peer = PeerInfo {
+                                // Keep the signed announcement itself alongside the derived
+                                // PeerInfo, since this node can't re-sign another node's
+                                // identity: it's what lets anti-entropy pulls re-serve this
+                                // record to a third peer later.
+                                let signed = SignedAnnouncement {
+                                    announcement: ann.clone(),
+                                    signature,
+                                };
+                                let mut peer = existing.unwrap_or_else(|| PeerInfo {
                                     node_id: ann.node_id.clone(),
-                                    name: ann.name,
-                                    capabilities: ann.capabilities,
+                                    name: ann.name.clone(),
+                                    capabilities: ann.capabilities.clone(),
                                     last_seen: protocol::now_secs(),
                                     ttl: ann.ttl,
                                     addresses: vec![],
-                                    metadata: ann.metadata,
-                                };
+                                    metadata: ann.metadata.clone(),
+                                    record_version: ann.record_version,
+                                    signed: signed.clone(),
+                                });
+                                peer.name = ann.name;
+                                peer.capabilities = ann.capabilities;
+                                peer.last_seen = protocol::now_secs();
+                                peer.ttl = ann.ttl;
+                                peer.metadata = ann.metadata;
+                                peer.record_version = ann.record_version;
+                                peer.merge_addresses(&ann.addresses);
+                                peer.signed = signed;
                                 let _ = store::upsert(peer);
                                 listen_state.peers_discovered.fetch_add(1, Ordering::Relaxed);
                             }
@@ -144,16 +418,16 @@ pub async fn run(interval_secs: u64) -> Result<()> {
     Ok(())
 }
 
-/// Accept incoming direct QUIC connections and handle messages.
-async fn accept_loop(endpoint: iroh::Endpoint, _state: Arc<DaemonState>) {
-    let node_id_str = endpoint.id().to_string();
+/// Accept incoming direct QUIC connections and route each one to whichever registered
+/// [`MessageHandler`] claims its negotiated ALPN.
+async fn accept_loop(endpoint: iroh::Endpoint, handlers: Arc<Vec<Arc<dyn MessageHandler>>>) {
     loop {
         let incoming = match endpoint.accept().await {
             Some(incoming) => incoming,
             None => break,
         };
 
-        let my_id = node_id_str.clone();
+        let handlers = handlers.clone();
         tokio::spawn(async move {
             let connection = match incoming.await {
                 Ok(conn) => conn,
@@ -163,51 +437,16 @@ async fn accept_loop(endpoint: iroh::Endpoint, _state: Arc<DaemonState>) {
                 }
             };
 
-            let alpn = connection.alpn();
-            if &*alpn != MSG_ALPN {
-                tracing::debug!(alpn = ?alpn, "unknown ALPN, ignoring");
-                return;
+            // Remember the remote socket address we observed this connection from, so a
+            // future dial can try it directly instead of relying solely on discovery.
+            if let Ok(remote_id) = connection.remote_node_id() {
+                let _ = store::remember_address(&remote_id.to_string(), connection.remote_address().to_string());
             }
 
-            match connection.accept_bi().await {
-                Ok((mut send, mut recv)) => {
-                    // Read length-prefixed message
-                    let mut len_buf = [0u8; 4];
-                    if recv.read_exact(&mut len_buf).await.is_err() {
-                        return;
-                    }
-                    let len = u32::from_be_bytes(len_buf) as usize;
-                    if len > 1024 * 1024 {
-                        return;
-                    }
-                    let mut buf = vec![0u8; len];
-                    if recv.read_exact(&mut buf).await.is_err() {
-                        return;
-                    }
-
-                    if let Ok(msg) = DirectMessage::from_bytes(&buf) {
-                        tracing::info!(from = %msg.from, "received direct message");
-                        eprintln!(
-                            "Message from {}: {}",
-                            &msg.from[..16.min(msg.from.len())],
-                            msg.content
-                        );
-
-                        // Send ack response
-                        let ack = DirectMessage {
-                            from: my_id.clone(),
-                            content: "received".to_string(),
-                            timestamp: protocol::now_secs(),
-                        };
-                        let ack_bytes = ack.to_bytes();
-                        let _ = send.write_all(&(ack_bytes.len() as u32).to_be_bytes()).await;
-                        let _ = send.write_all(&ack_bytes).await;
-                        let _ = send.finish();
-                    }
-                }
-                Err(e) => {
-                    tracing::debug!("failed to accept stream: {e}");
-                }
+            let alpn = connection.alpn();
+            match handlers.iter().find(|h| h.alpns().iter().any(|a| *a == &alpn[..])) {
+                Some(handler) => handler.handle(&alpn, connection).await,
+                None => tracing::debug!(alpn = ?alpn, "no handler registered for ALPN, ignoring"),
             }
         });
     }
@@ -0,0 +1,30 @@
+//! Pluggable handlers for application-defined ALPN wire protocols.
+//!
+//! Borrows the custom-message-type idea from BOLT/rust-lightning's `CustomMessageHandler`:
+//! the daemon's accept loop no longer hardcodes [`MSG_ALPN`](crate::protocol::MSG_ALPN) and
+//! the RPC layer built on top of it. Instead it holds a list of [`MessageHandler`]s and
+//! routes each incoming connection to whichever one claims the negotiated ALPN, so an
+//! embedder (e.g. an OpenClaw agent) can register its own wire protocol alongside the
+//! built-in gossip/discovery machinery without forking clawnet.
+
+use std::future::Future;
+use std::pin::Pin;
+
+use iroh::endpoint::Connection;
+
+/// Claims one or more ALPN identifiers and handles connections negotiated on them.
+///
+/// `handle` takes ownership of the accepted `Connection`; most implementations will loop
+/// accepting bi-streams off it for the life of the connection, the way the built-in RPC
+/// handler does.
+pub trait MessageHandler: Send + Sync {
+    /// ALPN identifiers this handler accepts.
+    fn alpns(&self) -> Vec<&[u8]>;
+
+    /// Handle one accepted connection negotiated on `alpn`.
+    fn handle<'a>(
+        &'a self,
+        alpn: &'a [u8],
+        connection: Connection,
+    ) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>>;
+}
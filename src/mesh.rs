@@ -0,0 +1,167 @@
+//! Persistent full-mesh peering manager, modeled on Netapp's `peering/fullmesh`.
+//!
+//! Dial-on-demand (a fresh `ClawNode` per `Send`/`Connect`) is expensive and leaves no way
+//! for a peer to reach this node unsolicited. [`MeshManager`] instead maintains one
+//! long-lived QUIC connection per known, non-expired peer in the store, reconnecting with
+//! backoff on failure, so [`MeshManager::send_to`] can reuse an existing link.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{bail, Result};
+use tokio::sync::Mutex;
+
+use crate::protocol::{RpcResponse, MSG_ALPN};
+use crate::rpc;
+use crate::store;
+
+/// Backoff schedule for reconnect attempts; the last entry repeats once exhausted.
+const RECONNECT_BACKOFF_SECS: &[u64] = &[1, 2, 4, 8, 16, 30];
+/// Give up reconnecting to a peer after this many failed attempts; `reconcile` will retry
+/// it again on its next pass if the peer is still in the store.
+const MAX_RECONNECT_ATTEMPTS: usize = 8;
+
+struct MeshLink {
+    connection: iroh::endpoint::Connection,
+}
+
+/// Maintains one persistent connection per known, non-expired peer. `on_transition(node_id,
+/// up)` fires whenever a link is established or torn down; the live mesh size itself is
+/// read with [`MeshManager::live_count`].
+pub struct MeshManager {
+    endpoint: iroh::Endpoint,
+    links: Mutex<HashMap<String, MeshLink>>,
+    /// Peers a `connect_with_backoff` task currently owns, so `reconcile` doesn't spawn a
+    /// second dialer for the same peer while the first is still mid-backoff (it only gets
+    /// inserted into `links` once it actually succeeds).
+    connecting: Mutex<HashSet<String>>,
+    on_transition: Box<dyn Fn(&str, bool) + Send + Sync>,
+}
+
+impl MeshManager {
+    pub fn new(
+        endpoint: iroh::Endpoint,
+        on_transition: impl Fn(&str, bool) + Send + Sync + 'static,
+    ) -> Arc<Self> {
+        Arc::new(Self {
+            endpoint,
+            links: Mutex::new(HashMap::new()),
+            connecting: Mutex::new(HashSet::new()),
+            on_transition: Box::new(on_transition),
+        })
+    }
+
+    /// Reconcile the mesh against the current peer store: drop links to peers that expired
+    /// or are no longer in the store, then dial any known, non-expired peer we don't already
+    /// have a link to (or aren't already dialing).
+    pub async fn reconcile(self: &Arc<Self>) {
+        let peers = store::all().unwrap_or_default();
+        let known: HashSet<String> = peers
+            .iter()
+            .filter(|p| !p.is_expired())
+            .map(|p| p.node_id.clone())
+            .collect();
+
+        let stale: Vec<String> = {
+            let mut links = self.links.lock().await;
+            let stale: Vec<String> = links
+                .keys()
+                .filter(|node_id| !known.contains(*node_id))
+                .cloned()
+                .collect();
+            for node_id in &stale {
+                links.remove(node_id);
+            }
+            stale
+        };
+        for node_id in stale {
+            (self.on_transition)(&node_id, false);
+        }
+
+        for peer in peers {
+            if peer.is_expired() || self.links.lock().await.contains_key(&peer.node_id) {
+                continue;
+            }
+            if !self.connecting.lock().await.insert(peer.node_id.clone()) {
+                continue;
+            }
+            let this = self.clone();
+            tokio::spawn(async move {
+                this.connect_with_backoff(peer.node_id, peer.addresses).await;
+            });
+        }
+    }
+
+    async fn connect_with_backoff(self: Arc<Self>, node_id: String, addresses: Vec<String>) {
+        let Ok(target) = node_id.parse::<iroh::EndpointId>() else {
+            self.connecting.lock().await.remove(&node_id);
+            return;
+        };
+        let direct_addresses: Vec<_> = addresses.iter().filter_map(|a| a.parse().ok()).collect();
+        if !direct_addresses.is_empty() {
+            let node_addr = iroh::NodeAddr::from_parts(target, None, direct_addresses);
+            let _ = self.endpoint.add_node_addr(node_addr);
+        }
+
+        for attempt in 0..MAX_RECONNECT_ATTEMPTS {
+            match self.endpoint.connect(target, MSG_ALPN).await {
+                Ok(connection) => {
+                    self.links
+                        .lock()
+                        .await
+                        .insert(node_id.clone(), MeshLink { connection });
+                    self.connecting.lock().await.remove(&node_id);
+                    (self.on_transition)(&node_id, true);
+                    return;
+                }
+                Err(e) => {
+                    tracing::debug!(peer = %node_id, attempt, "mesh connect attempt failed: {e}");
+                    let backoff = RECONNECT_BACKOFF_SECS
+                        [attempt.min(RECONNECT_BACKOFF_SECS.len() - 1)];
+                    tokio::time::sleep(Duration::from_secs(backoff)).await;
+                }
+            }
+        }
+        self.connecting.lock().await.remove(&node_id);
+    }
+
+    /// Call `method` on `node_id` over its persistent mesh link, reusing the connection
+    /// instead of dialing fresh. A link whose connection has already closed is dropped (and
+    /// its down transition fired, so `mesh_size`-style counters stay accurate) so the next
+    /// `reconcile` redials it, rather than leaving a dead entry in the map to fail the same
+    /// way again.
+    pub async fn call(&self, node_id: &str, method: &str, payload: Vec<u8>) -> Result<RpcResponse> {
+        let connection = self.links.lock().await.get(node_id).map(|l| l.connection.clone());
+        let Some(connection) = connection else {
+            bail!("no mesh link to {node_id}");
+        };
+        if let Some(reason) = connection.close_reason() {
+            self.links.lock().await.remove(node_id);
+            (self.on_transition)(node_id, false);
+            bail!("mesh link to {node_id} is closed: {reason}");
+        }
+        rpc::call(&connection, method, payload).await
+    }
+
+    /// Send `payload` to `node_id` over its persistent mesh link via the `message` RPC method.
+    pub async fn send_to(&self, node_id: &str, payload: Vec<u8>) -> Result<()> {
+        self.call(node_id, "message", payload).await?;
+        Ok(())
+    }
+
+    /// Current number of live mesh links, for `Status` to report mesh size.
+    pub async fn live_count(&self) -> usize {
+        self.links.lock().await.len()
+    }
+
+    /// Gracefully close every mesh link. Call this before sending a `Leave` so peers see an
+    /// orderly disconnect rather than a timeout.
+    pub async fn shutdown(&self) {
+        let mut links = self.links.lock().await;
+        for (node_id, link) in links.drain() {
+            link.connection.close(0u32.into(), b"shutdown");
+            (self.on_transition)(&node_id, false);
+        }
+    }
+}
@@ -1,5 +1,7 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
 
+use ed25519_dalek::Signature;
 use serde::{Deserialize, Serialize};
 
 /// Wire format for gossip bot announcements.
@@ -15,22 +17,106 @@ pub struct BotAnnouncement {
     pub mode: Option<String>,
     pub timestamp: u64,
     pub ttl: u64,
+    /// A `BTreeMap` rather than a `HashMap` so `announce_payload`'s `postcard` encoding is
+    /// deterministic across processes: signing and verification must see the same byte
+    /// order, and `HashMap`'s iteration order is randomized per-process.
     #[serde(default)]
-    pub metadata: HashMap<String, String>,
+    pub metadata: BTreeMap<String, String>,
+    /// Monotonically increasing CRDT record version (not to be confused with `version`,
+    /// the bot's semver). Used for last-writer-wins conflict resolution in the peer store
+    /// and as the anti-entropy digest key; see [`next_record_version`].
+    #[serde(default)]
+    pub record_version: u64,
+    /// Socket addresses the announcer has observed for itself (its own direct endpoints),
+    /// gossiped so peers have a fallback dial path when discovery alone isn't enough.
+    #[serde(default)]
+    pub addresses: Vec<String>,
+}
+
+/// An announcement paired with the signature that authenticates it. Used both for the
+/// gossiped `Announce` message and for records returned by the `pull` RPC method.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedAnnouncement {
+    pub announcement: BotAnnouncement,
+    pub signature: Vec<u8>,
+}
+
+/// Derive the next CRDT record version: the current wall clock in the high bits and a
+/// caller-owned counter in the low bits, so versions only go backwards if the clock does,
+/// and ties within the same second still order by counter.
+pub fn next_record_version(counter: &AtomicU64) -> u64 {
+    let tiebreak = counter.fetch_add(1, Ordering::Relaxed) & 0xF_FFFF;
+    (now_secs() << 20) | tiebreak
 }
 
 /// Gossip message envelope.
+///
+/// `Announce` and `Leave` both carry a `signature` covering the canonical
+/// `postcard` encoding of the rest of the variant, produced by the issuing
+/// node's ed25519 secret key. This stops a gossip participant from forging
+/// another bot's identity or evicting it with a spoofed `Leave`. Build signed
+/// messages with [`GossipMessage::signed_announce`] / [`signed_leave`], and
+/// check them with [`GossipMessage::verify`] before trusting the contents.
+///
+/// Anti-entropy pulls are *not* a `GossipMessage` variant: unlike `Announce`/`Leave`, a
+/// pull only concerns the requester and one sampled peer, so broadcasting it over the
+/// gossip topic would flood every other node with targeted repair traffic for no benefit.
+/// It's instead delivered point-to-point as a `pull` RPC call over a peer's full-mesh link;
+/// see [`crate::mesh::MeshManager`] and the daemon's anti-entropy task.
+///
+/// [`signed_leave`]: GossipMessage::signed_leave
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub enum GossipMessage {
-    Announce(BotAnnouncement),
+    Announce {
+        announcement: BotAnnouncement,
+        signature: Vec<u8>,
+    },
     Leave {
         node_id: String,
         timestamp: u64,
+        signature: Vec<u8>,
     },
 }
 
 impl GossipMessage {
+    /// Sign `announcement` with `signing_key` and wrap it as an `Announce` message.
+    pub fn signed_announce(announcement: BotAnnouncement, signing_key: &iroh::SecretKey) -> Self {
+        let signature = signing_key.sign(&announce_payload(&announcement)).to_vec();
+        GossipMessage::Announce {
+            announcement,
+            signature,
+        }
+    }
+
+    /// Sign a `Leave` for `node_id` with `signing_key`, so only the owning node can evict itself.
+    pub fn signed_leave(node_id: String, timestamp: u64, signing_key: &iroh::SecretKey) -> Self {
+        let signature = signing_key.sign(&leave_payload(&node_id, timestamp)).to_vec();
+        GossipMessage::Leave {
+            node_id,
+            timestamp,
+            signature,
+        }
+    }
+
+    /// Verify this message's signature against the public key encoded in its `node_id`.
+    ///
+    /// Returns `false` on any malformed `node_id`/signature as well as on a genuine
+    /// verification failure; callers should treat all of these as "reject the message".
+    pub fn verify(&self) -> bool {
+        match self {
+            GossipMessage::Announce {
+                announcement,
+                signature,
+            } => verify_signature(&announcement.node_id, &announce_payload(announcement), signature),
+            GossipMessage::Leave {
+                node_id,
+                timestamp,
+                signature,
+            } => verify_signature(node_id, &leave_payload(node_id, *timestamp), signature),
+        }
+    }
+
     /// Serialize to bytes for gossip wire format.
     pub fn to_bytes(&self) -> Vec<u8> {
         postcard::to_allocvec(self).expect("serialization failed")
@@ -42,6 +128,41 @@ impl GossipMessage {
     }
 }
 
+impl SignedAnnouncement {
+    /// Verify the signature over this record's announcement.
+    pub fn verify(&self) -> bool {
+        verify_signature(
+            &self.announcement.node_id,
+            &announce_payload(&self.announcement),
+            &self.signature,
+        )
+    }
+}
+
+/// Canonical encoding of an announcement, excluding the signature, used for both signing and
+/// verification.
+fn announce_payload(announcement: &BotAnnouncement) -> Vec<u8> {
+    postcard::to_allocvec(announcement).expect("serialization failed")
+}
+
+/// Canonical encoding of a `Leave`'s signed fields.
+fn leave_payload(node_id: &str, timestamp: u64) -> Vec<u8> {
+    postcard::to_allocvec(&(node_id, timestamp)).expect("serialization failed")
+}
+
+/// Decode `node_id` as an ed25519 public key and check `signature` over `payload`.
+fn verify_signature(node_id: &str, payload: &[u8], signature: &[u8]) -> bool {
+    let Ok(endpoint_id) = node_id.parse::<iroh::EndpointId>() else {
+        return false;
+    };
+    let Ok(sig_bytes) = <[u8; 64]>::try_from(signature) else {
+        return false;
+    };
+    endpoint_id
+        .verify(payload, &Signature::from_bytes(&sig_bytes))
+        .is_ok()
+}
+
 /// Cached peer record for the local peer store.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PeerInfo {
@@ -53,10 +174,39 @@ pub struct PeerInfo {
     #[serde(default)]
     pub addresses: Vec<String>,
     #[serde(default)]
-    pub metadata: HashMap<String, String>,
+    pub metadata: BTreeMap<String, String>,
+    /// CRDT record version this entry was last updated from; see [`BotAnnouncement::record_version`].
+    /// The store keeps only the highest-version record per `node_id` (last-writer-wins).
+    #[serde(default)]
+    pub record_version: u64,
+    /// The signed announcement this record was learned from, kept verbatim so anti-entropy
+    /// pulls can re-serve it to other peers. A node can only sign its own announcements, so
+    /// this is what lets a `pull` RPC response carry third-party records instead of just the
+    /// responder's own.
+    pub signed: SignedAnnouncement,
+}
+
+impl From<&SignedAnnouncement> for PeerInfo {
+    fn from(signed: &SignedAnnouncement) -> Self {
+        let ann = &signed.announcement;
+        PeerInfo {
+            node_id: ann.node_id.clone(),
+            name: ann.name.clone(),
+            capabilities: ann.capabilities.clone(),
+            last_seen: now_secs(),
+            ttl: ann.ttl,
+            addresses: ann.addresses.iter().take(PeerInfo::MAX_ADDRESSES).cloned().collect(),
+            metadata: ann.metadata.clone(),
+            record_version: ann.record_version,
+            signed: signed.clone(),
+        }
+    }
 }
 
 impl PeerInfo {
+    /// Most-recently-seen addresses kept per peer; older entries are evicted first.
+    pub const MAX_ADDRESSES: usize = 5;
+
     /// Check if this peer record has expired.
     pub fn is_expired(&self) -> bool {
         let now = std::time::SystemTime::now()
@@ -65,6 +215,32 @@ impl PeerInfo {
             .as_secs();
         now > self.last_seen + self.ttl
     }
+
+    /// True once `timeout_secs` have passed since this peer was last seen. Unlike
+    /// [`PeerInfo::is_expired`] (which waits out the announced TTL), this gives a much
+    /// faster liveness signal for `Peers --online` and `Status` without requiring the
+    /// peer to re-announce.
+    pub fn is_dead(&self, timeout_secs: u64) -> bool {
+        now_secs().saturating_sub(self.last_seen) > timeout_secs
+    }
+
+    /// Record `addr` as the most-recently-seen address for this peer, moving it to the
+    /// front and evicting the oldest entry once there are more than [`Self::MAX_ADDRESSES`].
+    pub fn remember_address(&mut self, addr: String) {
+        self.addresses.retain(|a| a != &addr);
+        self.addresses.insert(0, addr);
+        self.addresses.truncate(Self::MAX_ADDRESSES);
+    }
+
+    /// Merge addresses gossiped in an announcement into this record's MRU address list,
+    /// most-recent first. The gossiped addresses go ahead of any already held, including
+    /// ones learned from direct observation, which are evicted oldest-first past
+    /// [`Self::MAX_ADDRESSES`].
+    pub fn merge_addresses(&mut self, gossiped: &[String]) {
+        for addr in gossiped.iter().rev() {
+            self.remember_address(addr.clone());
+        }
+    }
 }
 
 /// Message sent over direct QUIC connections.
@@ -85,6 +261,83 @@ impl DirectMessage {
     }
 }
 
+/// Request envelope for the typed RPC layer carried over `MSG_ALPN`. `method` selects the
+/// handler registered in the daemon's `rpc::RpcRegistry`; `payload` is an opaque `postcard`
+/// encoding of that method's argument type. `id` correlates this request with its response
+/// so multiple concurrent bi-streams on one connection can be matched up by the caller.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcRequest {
+    pub id: u64,
+    pub method: String,
+    pub payload: Vec<u8>,
+}
+
+/// Response to an [`RpcRequest`], carrying either the handler's `postcard`-encoded return
+/// value or an error message.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcResponse {
+    pub id: u64,
+    pub result: Result<Vec<u8>, String>,
+}
+
+impl RpcRequest {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        postcard::to_allocvec(self).expect("serialization failed")
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> anyhow::Result<Self> {
+        postcard::from_bytes(bytes).map_err(Into::into)
+    }
+}
+
+impl RpcResponse {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        postcard::to_allocvec(self).expect("serialization failed")
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> anyhow::Result<Self> {
+        postcard::from_bytes(bytes).map_err(Into::into)
+    }
+}
+
+/// Request to relay `payload` to `target` over the daemon's full-mesh link, handled by the
+/// `relay` RPC method. Lets a short-lived CLI process (`clawnet send`) hand a message to an
+/// already-running daemon instead of dialing the target itself, reusing whatever standing
+/// connection [`crate::mesh::MeshManager`] already maintains.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelayRequest {
+    pub target: String,
+    pub payload: Vec<u8>,
+}
+
+impl RelayRequest {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        postcard::to_allocvec(self).expect("serialization failed")
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> anyhow::Result<Self> {
+        postcard::from_bytes(bytes).map_err(Into::into)
+    }
+}
+
+/// Periodic on-disk snapshot of a running daemon's health, written by
+/// [`crate::daemon::run_with_handlers`] and read by `clawnet status`/`clawnet send`. A
+/// separate, short-lived process can't reach into the daemon's in-memory `DaemonState` or
+/// `MeshManager`, so this file is the handoff point. `updated_at` lets a reader tell a live
+/// daemon from a stale file left by one that didn't shut down cleanly.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DaemonStatus {
+    pub node_id: String,
+    /// The daemon's own observed direct addresses, so a reader can dial it without relying
+    /// on discovery.
+    pub addresses: Vec<String>,
+    pub start_time: u64,
+    pub updated_at: u64,
+    pub announcements_sent: u64,
+    pub peers_discovered: u64,
+    pub mesh_size: u64,
+}
+
 /// ALPN protocol identifier for direct messaging.
 pub const MSG_ALPN: &[u8] = b"clawnet/msg/1";
 
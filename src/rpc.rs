@@ -0,0 +1,195 @@
+//! Typed request/response RPC layer over [`MSG_ALPN`](crate::protocol::MSG_ALPN).
+//!
+//! Modeled on Netapp/Garage's endpoint model: a [`RpcRegistry`] maps method names to
+//! handlers, and [`call`] drives the client side of one request/response round trip over
+//! a bi-directional stream. A connection may have many concurrent bi-streams in flight, so
+//! each request carries a correlation id.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use anyhow::{bail, Context, Result};
+use iroh::endpoint::Connection;
+
+use crate::handler::MessageHandler;
+use crate::protocol::{RpcRequest, RpcResponse, MSG_ALPN};
+
+/// A registered RPC method handler: takes the request payload, returns the response payload.
+pub type RpcHandler =
+    Arc<dyn Fn(Vec<u8>) -> Pin<Box<dyn Future<Output = Result<Vec<u8>>> + Send>> + Send + Sync>;
+
+/// Maps RPC method names to handlers. The daemon populates one at startup with its
+/// built-in methods, then shares it across accepted connections.
+#[derive(Clone, Default)]
+pub struct RpcRegistry {
+    handlers: HashMap<String, RpcHandler>,
+}
+
+impl RpcRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a handler for `method`, overwriting any existing registration.
+    pub fn register<F, Fut>(&mut self, method: &str, handler: F)
+    where
+        F: Fn(Vec<u8>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<Vec<u8>>> + Send + 'static,
+    {
+        self.handlers.insert(
+            method.to_string(),
+            Arc::new(move |payload| Box::pin(handler(payload))),
+        );
+    }
+
+    /// Dispatch `request` to its registered handler and build the matching response.
+    pub async fn dispatch(&self, request: RpcRequest) -> RpcResponse {
+        let result = match self.handlers.get(&request.method) {
+            Some(handler) => handler(request.payload).await.map_err(|e| e.to_string()),
+            None => Err(format!("unknown method: {}", request.method)),
+        };
+        RpcResponse {
+            id: request.id,
+            result,
+        }
+    }
+}
+
+static NEXT_REQUEST_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Call `method` on the peer at the other end of `connection`, opening a fresh
+/// bi-directional stream so it can run alongside any other in-flight calls. Writes the
+/// length-prefixed request and awaits the matching response.
+pub async fn call(
+    connection: &iroh::endpoint::Connection,
+    method: &str,
+    payload: Vec<u8>,
+) -> Result<RpcResponse> {
+    let id = NEXT_REQUEST_ID.fetch_add(1, Ordering::Relaxed);
+    let request = RpcRequest {
+        id,
+        method: method.to_string(),
+        payload,
+    };
+
+    let (mut send, mut recv) = connection
+        .open_bi()
+        .await
+        .context("failed to open RPC stream")?;
+
+    let bytes = request.to_bytes();
+    send.write_all(&(bytes.len() as u32).to_be_bytes())
+        .await
+        .context("failed to send RPC request length")?;
+    send.write_all(&bytes)
+        .await
+        .context("failed to send RPC request")?;
+    send.finish().context("failed to finish RPC request stream")?;
+
+    let response = read_response(&mut recv).await?;
+    if response.id != id {
+        bail!(
+            "RPC response id mismatch: expected {id}, got {}",
+            response.id
+        );
+    }
+    Ok(response)
+}
+
+async fn read_response(recv: &mut iroh::endpoint::RecvStream) -> Result<RpcResponse> {
+    let mut len_buf = [0u8; 4];
+    recv.read_exact(&mut len_buf)
+        .await
+        .context("failed to read RPC response length")?;
+    let len = u32::from_be_bytes(len_buf) as usize;
+    if len > 1024 * 1024 {
+        bail!("RPC response too large");
+    }
+    let mut buf = vec![0u8; len];
+    recv.read_exact(&mut buf)
+        .await
+        .context("failed to read RPC response")?;
+    RpcResponse::from_bytes(&buf)
+}
+
+/// Read one length-prefixed [`RpcRequest`] off an accepted bi-stream, dispatch it through
+/// `registry`, and write back the length-prefixed response. Used by the daemon's accept
+/// loop, once per bi-stream, so a connection can carry several concurrent calls.
+pub async fn serve_one(
+    registry: &RpcRegistry,
+    send: &mut iroh::endpoint::SendStream,
+    recv: &mut iroh::endpoint::RecvStream,
+) -> Result<()> {
+    let mut len_buf = [0u8; 4];
+    recv.read_exact(&mut len_buf)
+        .await
+        .context("failed to read RPC request length")?;
+    let len = u32::from_be_bytes(len_buf) as usize;
+    if len > 1024 * 1024 {
+        bail!("RPC request too large");
+    }
+    let mut buf = vec![0u8; len];
+    recv.read_exact(&mut buf)
+        .await
+        .context("failed to read RPC request")?;
+    let request = RpcRequest::from_bytes(&buf)?;
+
+    let response = registry.dispatch(request).await;
+    let bytes = response.to_bytes();
+    send.write_all(&(bytes.len() as u32).to_be_bytes())
+        .await
+        .context("failed to send RPC response length")?;
+    send.write_all(&bytes)
+        .await
+        .context("failed to send RPC response")?;
+    send.finish().context("failed to finish RPC response stream")?;
+    Ok(())
+}
+
+/// The built-in [`MessageHandler`] for clawnet's RPC layer: claims [`MSG_ALPN`] and serves
+/// every bi-stream opened on an accepted connection against a shared [`RpcRegistry`].
+pub struct RpcMessageHandler {
+    registry: Arc<RpcRegistry>,
+}
+
+impl RpcMessageHandler {
+    pub fn new(registry: Arc<RpcRegistry>) -> Self {
+        Self { registry }
+    }
+}
+
+impl MessageHandler for RpcMessageHandler {
+    fn alpns(&self) -> Vec<&[u8]> {
+        vec![MSG_ALPN]
+    }
+
+    fn handle<'a>(
+        &'a self,
+        _alpn: &'a [u8],
+        connection: Connection,
+    ) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
+        Box::pin(async move {
+            // A connection may carry several concurrent calls, so dispatch each
+            // accepted bi-stream on its own task instead of handling just one.
+            loop {
+                let (mut send, mut recv) = match connection.accept_bi().await {
+                    Ok(streams) => streams,
+                    Err(e) => {
+                        tracing::debug!("failed to accept stream: {e}");
+                        break;
+                    }
+                };
+
+                let registry = self.registry.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = serve_one(&registry, &mut send, &mut recv).await {
+                        tracing::debug!("RPC call failed: {e}");
+                    }
+                });
+            }
+        })
+    }
+}